@@ -80,17 +80,102 @@ extern crate rand;
 
 use core::slice::Chunks;
 
-pub type Base64Result = Result<(), ()>;
+pub type Base64Result = Result<(), Base64Error>;
+
+/// Describes what went wrong, and where, while decoding.
+///
+/// `offset` is the absolute index into the original input, counting any
+/// line-ending bytes skipped by [`Base64Decoder::from_lines`](struct.Base64Decoder.html#method.from_lines).
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Base64Error {
+    /// The byte at `offset` is not part of the selected alphabet.
+    InvalidByte {
+        offset: usize,
+        byte: u8,
+    },
+    /// The input is not a supported length for the decoder's padding mode.
+    InvalidLength,
+    /// A `=` padding character appears out of place, starting at `offset`.
+    InvalidPadding {
+        offset: usize,
+    },
+}
+
+/// Describes the 64-character table used to map 6-bit values to their
+/// encoded byte, and back again.
+///
+/// The standard alphabet is used unless one of the other constructors is
+/// selected, allowing `Base64Encoder`/`Base64Decoder` to produce or consume
+/// the URL-safe, bcrypt, crypt, or sha-crypt variants without duplicating
+/// the chunking logic.
+#[derive(Copy, Clone)]
+pub struct Alphabet {
+    table: [u8; 64],
+}
+
+impl Alphabet {
+    /// The standard alphabet from RFC 4648 (`A-Za-z0-9+/`).
+    pub fn standard() -> Alphabet {
+        Alphabet { table: *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/" }
+    }
+
+    /// The URL- and filename-safe alphabet from RFC 4648 (`A-Za-z0-9-_`).
+    pub fn url_safe() -> Alphabet {
+        Alphabet { table: *b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_" }
+    }
+
+    /// The alphabet used by bcrypt password hashes (`./A-Za-z0-9`).
+    pub fn bcrypt() -> Alphabet {
+        Alphabet { table: *b"./ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789" }
+    }
+
+    /// The alphabet used by traditional `crypt(3)` hashes (`./0-9A-Za-z`).
+    pub fn crypt() -> Alphabet {
+        Alphabet { table: *b"./0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz" }
+    }
+
+    /// The alphabet used by sha-crypt (`$5$`/`$6$`) password hashes.
+    ///
+    /// This shares its character ordering with [`crypt`](#method.crypt).
+    pub fn shacrypt() -> Alphabet {
+        Alphabet::crypt()
+    }
+
+    fn encode(&self, value: u8) -> u8 {
+        self.table[value as usize]
+    }
+
+    fn decode(&self, byte: u8) -> Option<u8> {
+        self.table.iter().position(|&c| c == byte).map(|i| i as u8)
+    }
+}
+
+/// Selects how 6-bit values are converted to and from their encoded byte.
+///
+/// `Table` drives the conversion from an [`Alphabet`](struct.Alphabet.html)
+/// and is the default. `ConstantTime` instead performs the conversion with
+/// branchless arithmetic on the standard alphabet, so that the time and
+/// memory-access pattern of the encoder/decoder do not depend on the value
+/// of the data being processed; it is intended for use with secret
+/// material such as private keys or password hashes.
+#[derive(Copy, Clone)]
+enum Engine {
+    Table(Alphabet),
+    ConstantTime,
+}
 
 pub struct Base64Encoder<'a> {
     input: Chunks<'a, u8>,
     output: EncodeIterator,
+    engine: Engine,
+    padding: bool,
 }
 
 // Small iterator to for an encoded "chunk" of 3 bytes -> 4 chars
 struct EncodeIterator {
     buffer: [u8; 4],
     idx: usize,
+    len: usize,
 }
 
 impl <'a> Base64Encoder<'a> {
@@ -105,14 +190,79 @@ impl <'a> Base64Encoder<'a> {
     /// let encoder = Base64Encoder::new(&buffer);
     /// ```
     pub fn new(input: &'a [u8]) -> Base64Encoder {
+        Base64Encoder::with_alphabet(input, Alphabet::standard())
+    }
+
+    /// Create a new Base64Encoder from the provided slice, using the given
+    /// alphabet instead of the standard one.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::{Base64Encoder, Alphabet};
+    ///
+    /// let buffer = [0u8, 1, 2, 3, 4, 5];
+    /// let encoder = Base64Encoder::with_alphabet(&buffer, Alphabet::url_safe());
+    /// ```
+    pub fn with_alphabet(input: &'a [u8], alphabet: Alphabet) -> Base64Encoder {
         Base64Encoder {
             input: input.chunks(3),
             output: EncodeIterator {
                 buffer: [0; 4],
                 idx: 4,
-            }
+                len: 4,
+            },
+            engine: Engine::Table(alphabet),
+            padding: true,
         }
     }
+
+    /// Create a new Base64Encoder that encodes using data-independent,
+    /// branchless arithmetic rather than table lookups or `match` arms.
+    ///
+    /// Use this constructor when encoding secret material (private keys,
+    /// password hashes, etc.) so that the time and memory-access pattern of
+    /// the encoder do not leak information about the bytes being encoded.
+    /// Only the standard alphabet is supported in this mode.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::Base64Encoder;
+    ///
+    /// let buffer = [0u8, 1, 2, 3, 4, 5];
+    /// let encoder = Base64Encoder::new_constant_time(&buffer);
+    /// ```
+    pub fn new_constant_time(input: &'a [u8]) -> Base64Encoder {
+        Base64Encoder {
+            input: input.chunks(3),
+            output: EncodeIterator {
+                buffer: [0; 4],
+                idx: 4,
+                len: 4,
+            },
+            engine: Engine::ConstantTime,
+            padding: true,
+        }
+    }
+
+    /// Omit the trailing `=` padding characters from a short final group, as
+    /// used by the base64url variant without padding.
+    ///
+    /// Composes with [`with_alphabet`](#method.with_alphabet) and
+    /// [`new_constant_time`](#method.new_constant_time), so that e.g.
+    /// unpadded URL-safe output (as used by JWTs) doesn't need its own
+    /// constructor.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::{Base64Encoder, Alphabet};
+    ///
+    /// let encoder = Base64Encoder::new(b"foobar").no_padding();
+    /// let jwt_segment = Base64Encoder::with_alphabet(b"foobar", Alphabet::url_safe()).no_padding();
+    /// ```
+    pub fn no_padding(mut self) -> Base64Encoder<'a> {
+        self.padding = false;
+        self
+    }
 }
 
 impl <'a> Iterator for Base64Encoder<'a> {
@@ -123,7 +273,7 @@ impl <'a> Iterator for Base64Encoder<'a> {
             Some(n)
         } else {
             if let Some(chunk) = self.input.next() {
-                encode_chunk(chunk, &mut self.output);
+                encode_chunk(chunk, self.engine, self.padding, &mut self.output);
                 self.output.next()
             } else {
                 None
@@ -136,7 +286,7 @@ impl Iterator for EncodeIterator {
     type Item = u8;
 
     fn next(&mut self) -> Option<u8> {
-        if self.idx < self.buffer.len() {
+        if self.idx < self.len {
             self.idx += 1;
             Some(self.buffer[self.idx - 1])
         } else {
@@ -145,24 +295,172 @@ impl Iterator for EncodeIterator {
     }
 }
 
-fn encode_chunk(chunk: &[u8], output: &mut EncodeIterator) {
+/// The line ending inserted by [`Base64Lines`](struct.Base64Lines.html).
+#[derive(Copy, Clone, PartialEq)]
+pub enum LineEnding {
+    /// A bare `\n`, as used by PEM.
+    LF,
+    /// A `\r\n` pair, as required by MIME.
+    CRLF,
+}
+
+impl LineEnding {
+    fn bytes(&self) -> &'static [u8] {
+        match *self {
+            LineEnding::LF => b"\n",
+            LineEnding::CRLF => b"\r\n",
+        }
+    }
+}
+
+/// Wraps a byte iterator (typically a [`Base64Encoder`](struct.Base64Encoder.html))
+/// to insert a line ending after every `width` emitted characters, as
+/// required when generating PEM or MIME payloads.
+///
+/// No trailing line ending is emitted after the final, possibly partial,
+/// line.
+///
+/// # Example
+/// ```
+/// use base64::{Base64Encoder, Base64Lines, LineEnding};
+///
+/// let encoder = Base64Encoder::new(&[0u8; 48]);
+/// let wrapped: Vec<u8> = Base64Lines::new(encoder, 16, LineEnding::LF).collect();
+/// ```
+pub struct Base64Lines<I: Iterator<Item = u8>> {
+    inner: core::iter::Peekable<I>,
+    width: usize,
+    ending: LineEnding,
+    column: usize,
+    // Number of line-ending bytes still to be emitted; 0 when not currently
+    // in the middle of emitting one.
+    ending_remaining: usize,
+}
+
+impl <I: Iterator<Item = u8>> Base64Lines<I> {
+    /// Create a new line-wrapping adapter over `inner`, inserting `ending`
+    /// after every `width` characters.
+    pub fn new(inner: I, width: usize, ending: LineEnding) -> Base64Lines<I> {
+        Base64Lines {
+            inner: inner.peekable(),
+            width: width,
+            ending: ending,
+            column: 0,
+            ending_remaining: 0,
+        }
+    }
+}
+
+impl <I: Iterator<Item = u8>> Iterator for Base64Lines<I> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let ending = self.ending.bytes();
+
+        if self.ending_remaining > 0 {
+            let b = ending[ending.len() - self.ending_remaining];
+            self.ending_remaining -= 1;
+            if self.ending_remaining == 0 {
+                self.column = 0;
+            }
+            return Some(b);
+        }
+
+        if self.column == self.width && self.inner.peek().is_some() {
+            self.ending_remaining = ending.len() - 1;
+            if self.ending_remaining == 0 {
+                self.column = 0;
+            }
+            return Some(ending[0]);
+        }
+
+        match self.inner.next() {
+            Some(b) => {
+                self.column += 1;
+                Some(b)
+            }
+            None => None,
+        }
+    }
+}
+
+/// Encodes a byte slice directly into a `core::fmt::Formatter`, without
+/// first draining a [`Base64Encoder`](struct.Base64Encoder.html) into a
+/// buffer.
+///
+/// This makes it possible to `write!` base64 output, or build a `String`
+/// with it, in `no_std` contexts that have a `core::fmt::Write` sink but no
+/// heap-backed encoder buffer.
+///
+/// # Example
+/// ```
+/// use base64::Base64Display;
+///
+/// assert_eq!(format!("{}", Base64Display::new(b"foobar")), "Zm9vYmFy");
+/// ```
+pub struct Base64Display<'a> {
+    input: &'a [u8],
+}
+
+impl <'a> Base64Display<'a> {
+    /// Create a new Base64Display that encodes the provided slice using the
+    /// standard alphabet.
+    pub fn new(input: &'a [u8]) -> Base64Display<'a> {
+        Base64Display { input: input }
+    }
+}
+
+impl <'a> core::fmt::Display for Base64Display<'a> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let mut output = EncodeIterator { buffer: [0; 4], idx: 4, len: 4 };
+        for chunk in self.input.chunks(3) {
+            encode_chunk(chunk, Engine::Table(Alphabet::standard()), true, &mut output);
+            let group = core::str::from_utf8(&output.buffer[..output.len]).unwrap();
+            match f.write_str(group) {
+                Ok(()) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(())
+    }
+}
+
+fn encode_chunk(chunk: &[u8], engine: Engine, padding: bool, output: &mut EncodeIterator) {
     let combined: u32 = combine_bytes(chunk);
     for (i, shift) in [18, 12, 6, 0].iter().enumerate() {
         let u: u8 = ((combined >> *shift) as u8) & 0b0011_1111;
-        output.buffer[i] = match u {
-             0...25 => b'A' + u,
-            26...51 => b'a' + u - 26,
-            52...61 => b'0' + u - 52,
-                 62 => b'+',
-                 63 => b'/',
-                  _ => unreachable!()
+        output.buffer[i] = match engine {
+            Engine::Table(alphabet) => alphabet.encode(u),
+            Engine::ConstantTime => encode_byte_ct(u),
         };
     }
-    if chunk.len() <= 1 { output.buffer[2] = b'='; }
-    if chunk.len() <= 2 { output.buffer[3] = b'='; }
+    output.len = if padding {
+        if chunk.len() <= 1 { output.buffer[2] = b'='; }
+        if chunk.len() <= 2 { output.buffer[3] = b'='; }
+        4
+    } else {
+        match chunk.len() {
+            1 => 2,
+            2 => 3,
+            _ => 4,
+        }
+    };
     output.idx = 0;
 }
 
+// Converts a single 6-bit value into its encoded byte using only
+// data-independent arithmetic, so that the instruction and memory-access
+// pattern do not depend on the value of `src`.
+fn encode_byte_ct(src: u8) -> u8 {
+    let src = src as i16;
+    let mut diff: i16 = 0x41;
+    diff += ((25 - src) >> 8) & 6;
+    diff -= ((51 - src) >> 8) & 75;
+    diff -= ((61 - src) >> 8) & 15;
+    diff += ((62 - src) >> 8) & 3;
+    (src + diff) as u8
+}
+
 // Combines up to 3 bytes into a u32.
 fn combine_bytes(bytes: &[u8]) -> u32 {
     0 | if bytes.len() >= 1 {
@@ -181,17 +479,38 @@ fn combine_bytes(bytes: &[u8]) -> u32 {
 }
 
 pub struct Base64Decoder<'a> {
-    input: Chunks<'a, u8>,
+    input: &'a [u8],
+    pos: usize,
     output: DecodeIterator,
     status: Base64Result,
+    engine: Engine,
+    padding: bool,
+    strip_line_endings: bool,
 }
 
-struct DecodeIterator {
+/// Iterator over the up-to-3 decoded bytes produced by a single decoded
+/// group, returned directly by [`Base64StreamDecoder::finish`](struct.Base64StreamDecoder.html#method.finish).
+pub struct DecodeIterator {
     buffer: [u8; 3],  // 4 characters generate 3 bytes
     idx: usize,
     len: usize,
 }
 
+// Converts a single encoded byte of the standard alphabet into its 6-bit
+// value using only data-independent arithmetic, returning -1 if `src` is
+// not part of the standard alphabet. The instruction and memory-access
+// pattern do not depend on the value of `src`.
+fn decode_byte_ct(src: u8) -> i16 {
+    let src = src as i16;
+    let mut ret: i16 = -1;
+    ret += (((64 - src) & (src - 91)) >> 8) & (src - 64);
+    ret += (((96 - src) & (src - 123)) >> 8) & (src - 70);
+    ret += (((47 - src) & (src - 58)) >> 8) & (src + 5);
+    ret += (((42 - src) & (src - 44)) >> 8) & 63;
+    ret += (((46 - src) & (src - 48)) >> 8) & 64;
+    ret
+}
+
 impl <'a> Base64Decoder<'a> {
 
     /// Create a new Base64Decoder from the provided slice.
@@ -204,17 +523,136 @@ impl <'a> Base64Decoder<'a> {
     /// let decoder = Base64Decoder::new(buffer);
     /// ```
     pub fn new(input: &'a [u8]) -> Base64Decoder {
+        Base64Decoder::with_alphabet(input, Alphabet::standard())
+    }
+
+    /// Create a new Base64Decoder from the provided slice, using the given
+    /// alphabet instead of the standard one.
+    ///
+    /// Characters belonging to a different alphabet (for example `+`/`/`
+    /// when decoding with [`Alphabet::url_safe`](struct.Alphabet.html#method.url_safe))
+    /// are rejected rather than silently accepted.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::{Base64Decoder, Alphabet};
+    ///
+    /// let buffer = b"Zm9vYmFy";
+    /// let decoder = Base64Decoder::with_alphabet(buffer, Alphabet::url_safe());
+    /// ```
+    pub fn with_alphabet(input: &'a [u8], alphabet: Alphabet) -> Base64Decoder {
+        Base64Decoder {
+            input: input,
+            pos: 0,
+            output: DecodeIterator {
+                buffer: [0; 3],
+                idx: 3,
+                len: 0,
+            },
+            status: Ok(()),
+            engine: Engine::Table(alphabet),
+            padding: true,
+            strip_line_endings: false,
+        }
+    }
+
+    /// Create a new Base64Decoder that decodes using data-independent,
+    /// branchless arithmetic rather than table lookups or `match` arms.
+    ///
+    /// Use this constructor when decoding secret material (private keys,
+    /// password hashes, etc.) so that the time and memory-access pattern of
+    /// the decoder do not leak information about the bytes being decoded.
+    /// Only the standard alphabet is supported in this mode.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::Base64Decoder;
+    ///
+    /// let buffer = b"Zm9vYmFy";
+    /// let decoder = Base64Decoder::new_constant_time(buffer);
+    /// ```
+    pub fn new_constant_time(input: &'a [u8]) -> Base64Decoder {
         Base64Decoder {
-            input: input.chunks(4),
+            input: input,
+            pos: 0,
             output: DecodeIterator {
                 buffer: [0; 3],
                 idx: 3,
                 len: 0,
             },
             status: Ok(()),
+            engine: Engine::ConstantTime,
+            padding: true,
+            strip_line_endings: false,
         }
     }
 
+    /// Accept an unpadded final group of 2 or 3 characters instead of
+    /// requiring the input to be a multiple of 4 characters long.
+    ///
+    /// Composes with [`with_alphabet`](#method.with_alphabet) and
+    /// [`new_constant_time`](#method.new_constant_time), so that e.g.
+    /// unpadded URL-safe input (as used by JWTs) doesn't need its own
+    /// constructor.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::{Base64Decoder, Alphabet};
+    ///
+    /// let decoder = Base64Decoder::new(b"Zm9vYmFy").no_padding();
+    /// let jwt_segment = Base64Decoder::with_alphabet(b"Zm9vYmFy", Alphabet::url_safe()).no_padding();
+    /// ```
+    pub fn no_padding(mut self) -> Base64Decoder<'a> {
+        self.padding = false;
+        self
+    }
+
+    /// Transparently skip `\r` and `\n` bytes in the input, allowing
+    /// line-wrapped PEM/MIME payloads produced by
+    /// [`Base64Lines`](struct.Base64Lines.html) to round-trip without being
+    /// flagged invalid.
+    ///
+    /// Composes with [`with_alphabet`](#method.with_alphabet) and
+    /// [`new_constant_time`](#method.new_constant_time), so that line-wrapped
+    /// non-standard alphabets (for example a bcrypt hash split across lines)
+    /// don't need their own constructor.
+    ///
+    /// # Example
+    /// ```
+    /// use base64::Base64Decoder;
+    ///
+    /// let decoder = Base64Decoder::new(b"Zm9v\nYmFy").from_lines();
+    /// ```
+    pub fn from_lines(mut self) -> Base64Decoder<'a> {
+        self.strip_line_endings = true;
+        self
+    }
+
+    // Pulls up to the next 4 significant (non-line-ending, when enabled)
+    // bytes from the input, returning the group, how many bytes it
+    // contains, and the absolute offset of its first byte in the original
+    // input (which may be well ahead of a naive `group * 4`, since
+    // line-ending bytes skipped along the way don't count towards the
+    // group). Returns `None` once the input is exhausted.
+    fn next_group(&mut self) -> Option<([u8; 4], usize, usize)> {
+        let mut buf = [0u8; 4];
+        let mut n = 0;
+        let mut offset = 0;
+        while n < 4 && self.pos < self.input.len() {
+            let b = self.input[self.pos];
+            self.pos += 1;
+            if self.strip_line_endings && (b == b'\r' || b == b'\n') {
+                continue;
+            }
+            if n == 0 {
+                offset = self.pos - 1;
+            }
+            buf[n] = b;
+            n += 1;
+        }
+        if n == 0 { None } else { Some((buf, n, offset)) }
+    }
+
     /// Check the decoder status for errors.
     ///
     /// Because results are returned as an iterator, and iterators do not
@@ -242,8 +680,27 @@ impl <'a> Iterator for Base64Decoder<'a> {
         if let Some(n) = self.output.next() {
             Some(n)
         } else {
-            if let Some(chunk) = self.input.next() {
-                self.status = decode_chunk(chunk, &mut self.output);
+            if let Some((group, n, offset)) = self.next_group() {
+                // decode_chunk reports `offset + i` for the group's i-th
+                // character; map that to its real index in the input, since
+                // line endings may have been skipped inside the group.
+                let (input, strip) = (self.input, self.strip_line_endings);
+                let locate = |o: usize| {
+                    let (mut pos, mut k) = (offset, o - offset);
+                    while k > 0 {
+                        pos += 1;
+                        let b = input[pos];
+                        if !(strip && (b == b'\r' || b == b'\n')) { k -= 1; }
+                    }
+                    pos
+                };
+                self.status = match decode_chunk(&group[..n], offset, self.engine, self.padding, &mut self.output) {
+                    Err(Base64Error::InvalidByte { offset: o, byte: b }) =>
+                        Err(Base64Error::InvalidByte { offset: locate(o), byte: b }),
+                    Err(Base64Error::InvalidPadding { offset: o }) =>
+                        Err(Base64Error::InvalidPadding { offset: locate(o) }),
+                    status => status,
+                };
                 if self.status.is_ok() {
                     self.output.next()
                 } else {
@@ -269,6 +726,160 @@ impl Iterator for DecodeIterator {
     }
 }
 
+/// A decoder that accepts input in arbitrary fragments, for use when bytes
+/// arrive incrementally (for example from a socket or a reader) and don't
+/// necessarily land on 4-character boundaries.
+///
+/// Each fragment is supplied through [`feed`](#method.feed), which decodes
+/// as many complete 4-character groups as it can and retains the rest (0-3
+/// leftover characters) until the next call. Once all input has been
+/// supplied, call [`finish`](#method.finish) to validate or decode the
+/// final group.
+///
+/// # Example
+/// ```
+/// use base64::Base64StreamDecoder;
+///
+/// let mut decoder = Base64StreamDecoder::new();
+/// let first: Vec<u8> = decoder.feed(b"Zm9v").collect();
+/// let second: Vec<u8> = decoder.feed(b"YmFy").collect();
+/// let rest: Vec<u8> = decoder.finish().collect();
+///
+/// assert_eq!(first, b"foo");
+/// assert_eq!(second, b"bar");
+/// assert!(rest.is_empty());
+/// assert!(decoder.status().is_ok());
+/// ```
+pub struct Base64StreamDecoder {
+    staging: [u8; 4],
+    fill: usize,
+    engine: Engine,
+    padding: bool,
+    status: Base64Result,
+    group: usize,
+}
+
+impl Base64StreamDecoder {
+    /// Create a new, empty Base64StreamDecoder using the standard alphabet.
+    pub fn new() -> Base64StreamDecoder {
+        Base64StreamDecoder::with_alphabet(Alphabet::standard())
+    }
+
+    /// Create a new, empty Base64StreamDecoder using the given alphabet.
+    pub fn with_alphabet(alphabet: Alphabet) -> Base64StreamDecoder {
+        Base64StreamDecoder {
+            staging: [0; 4],
+            fill: 0,
+            engine: Engine::Table(alphabet),
+            padding: true,
+            status: Ok(()),
+            group: 0,
+        }
+    }
+
+    /// Accept a final group of 2 or 3 characters at [`finish`](#method.finish)
+    /// instead of requiring the total fed length to be a multiple of 4.
+    ///
+    /// Composes with [`with_alphabet`](#method.with_alphabet), mirroring
+    /// [`Base64Decoder::no_padding`](struct.Base64Decoder.html#method.no_padding).
+    ///
+    /// # Example
+    /// ```
+    /// use base64::Base64StreamDecoder;
+    ///
+    /// let mut decoder = Base64StreamDecoder::new().no_padding();
+    /// let first: Vec<u8> = decoder.feed(b"Zm9v").collect();
+    /// let second: Vec<u8> = decoder.feed(b"Yg").collect();
+    /// let rest: Vec<u8> = decoder.finish().collect();
+    ///
+    /// assert_eq!(first, b"foo");
+    /// assert!(second.is_empty());
+    /// assert_eq!(rest, b"b");
+    /// assert!(decoder.status().is_ok());
+    /// ```
+    pub fn no_padding(mut self) -> Base64StreamDecoder {
+        self.padding = false;
+        self
+    }
+
+    /// Feed another fragment of input, returning an iterator over the
+    /// bytes decoded from any complete 4-character groups. Leftover
+    /// characters (0-3) are retained internally across calls.
+    pub fn feed<'d, 'i>(&'d mut self, input: &'i [u8]) -> Base64Feed<'d, 'i> {
+        Base64Feed {
+            decoder: self,
+            input: input,
+            pos: 0,
+            output: DecodeIterator { buffer: [0; 3], idx: 3, len: 0 },
+        }
+    }
+
+    /// Finalize the stream, decoding or validating any residual characters
+    /// retained since the last `feed` call.
+    pub fn finish(&mut self) -> DecodeIterator {
+        let mut output = DecodeIterator { buffer: [0; 3], idx: 3, len: 0 };
+        if self.fill > 0 {
+            let group = self.staging;
+            let n = self.fill;
+            self.fill = 0;
+            let offset = self.group * 4;
+            self.group += 1;
+            let status = decode_chunk(&group[..n], offset, self.engine, self.padding, &mut output);
+            if self.status.is_ok() { self.status = status; }
+        }
+        output
+    }
+
+    /// Check the decoder status for errors.
+    ///
+    /// As with [`Base64Decoder`](struct.Base64Decoder.html), only the first
+    /// error encountered is reported.
+    pub fn status(&self) -> Base64Result {
+        Clone::clone(&self.status)
+    }
+}
+
+/// Iterator over the bytes decoded from a single [`Base64StreamDecoder::feed`](struct.Base64StreamDecoder.html#method.feed)
+/// call.
+pub struct Base64Feed<'d, 'i> {
+    decoder: &'d mut Base64StreamDecoder,
+    input: &'i [u8],
+    pos: usize,
+    output: DecodeIterator,
+}
+
+impl <'d, 'i> Iterator for Base64Feed<'d, 'i> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        loop {
+            if let Some(n) = self.output.next() {
+                return Some(n);
+            }
+
+            while self.decoder.fill < 4 && self.pos < self.input.len() {
+                self.decoder.staging[self.decoder.fill] = self.input[self.pos];
+                self.decoder.fill += 1;
+                self.pos += 1;
+            }
+
+            if self.decoder.fill < 4 {
+                return None;
+            }
+
+            let group = self.decoder.staging;
+            self.decoder.fill = 0;
+            let offset = self.decoder.group * 4;
+            self.decoder.group += 1;
+            let status = decode_chunk(&group[..], offset, self.decoder.engine, true, &mut self.output);
+            if self.decoder.status.is_ok() { self.decoder.status = status; }
+            if status.is_err() {
+                return None;
+            }
+        }
+    }
+}
+
 #[derive(Copy, Clone, PartialEq)]
 enum Decoded {
     Value(u8),
@@ -276,38 +887,58 @@ enum Decoded {
     Invalid,
 }
 
-fn decode_chunk(chunk: &[u8], output: &mut DecodeIterator) -> Base64Result {
+fn decode_chunk(chunk: &[u8], offset: usize, engine: Engine, padding: bool, output: &mut DecodeIterator) -> Base64Result {
 
 
     use Decoded::*;
 
-    let mut tmp: [Decoded; 4] = [Decoded::Invalid; 4];
+    // In padding mode, a chunk shorter than 4 characters is always invalid.
+    // With padding disabled, a short final chunk of 2 or 3 characters is a
+    // valid terminal group, but a single leftover character can't decode to
+    // anything.
+    if padding {
+        if chunk.len() != 4 {
+            return Err(Base64Error::InvalidLength);
+        }
+    } else if chunk.len() < 2 {
+        return Err(Base64Error::InvalidLength);
+    }
+
+    // Positions beyond `chunk.len()` only occur with padding disabled, and
+    // are treated the same as explicit `=` padding.
+    let mut tmp: [Decoded; 4] = [Decoded::Padding; 4];
 
     for (i, value) in chunk.iter().enumerate() {
-        tmp[i] = match *value {
-            b'A'...b'Z' => Value(value - 0x41),
-            b'a'...b'z' => Value(value - 0x47),
-            b'0'...b'9' => Value(value + 0x04),
-            b'+' | b'-' => Value(0x3E),
-            b'/' | b'_' => Value(0x3F),
-                   b'=' => Padding,
-                      _ => Invalid
+        tmp[i] = if *value == b'=' {
+            Padding
+        } else {
+            match engine {
+                Engine::Table(alphabet) => match alphabet.decode(*value) {
+                    Some(v) => Value(v),
+                    None => Invalid,
+                },
+                Engine::ConstantTime => {
+                    let v = decode_byte_ct(*value);
+                    if v < 0 { Invalid } else { Value(v as u8) }
+                }
+            }
         };
     }
 
-    // There should always be chunks of 4 characters
-    if tmp.iter().any(|x| match *x { Invalid => true, _ => false }) {
-        return Err(());
+    for (i, x) in tmp.iter().enumerate() {
+        if let Invalid = *x {
+            return Err(Base64Error::InvalidByte { offset: offset + i, byte: chunk[i] });
+        }
     }
 
     // Only positions 2 and 3 can be padding
     if tmp[0] == Padding || tmp[1] == Padding {
-        return Err(());
+        return Err(Base64Error::InvalidPadding { offset: offset });
     }
 
     // Position 2 can only be padding if position 3 is padding
     if tmp[2] == Padding && tmp[3] != Padding {
-        return Err(());
+        return Err(Base64Error::InvalidPadding { offset: offset + 2 });
     }
 
     if let (Value(a), Value(b)) = (tmp[0], tmp[1]) {
@@ -380,6 +1011,178 @@ mod test {
         test_wrapper("Z===");
     }
 
+    #[test]
+    fn test_decoder_error_offsets() {
+        let mut decoder = Base64Decoder::new(b"Zm9v$mFy");
+        while let Some(_) = decoder.next() { }
+        assert_eq!(decoder.status(), Err(Base64Error::InvalidByte { offset: 4, byte: b'$' }));
+
+        let mut decoder = Base64Decoder::new(b"Zm9vY===");
+        while let Some(_) = decoder.next() { }
+        assert_eq!(decoder.status(), Err(Base64Error::InvalidPadding { offset: 4 }));
+
+        let mut decoder = Base64Decoder::new(b"Zm9vYmF");
+        while let Some(_) = decoder.next() { }
+        assert_eq!(decoder.status(), Err(Base64Error::InvalidLength));
+    }
+
+    #[test]
+    fn test_decoder_error_offsets_from_lines() {
+        // The '$' is at index 7 of the original input, even though only 6
+        // of the preceding bytes are significant base64 characters.
+        let mut decoder = Base64Decoder::new(b"Zm9v\nYm$y").from_lines();
+        while let Some(_) = decoder.next() { }
+        assert_eq!(decoder.status(), Err(Base64Error::InvalidByte { offset: 7, byte: b'$' }));
+    }
+
+    #[test]
+    fn test_alphabet_url_safe() {
+        let encoder = Base64Encoder::with_alphabet(b"\xfb\xff\xbf", Alphabet::url_safe());
+        let encoded: Vec<u8> = encoder.collect();
+        assert_eq!(encoded, b"-_-_");
+
+        let decoder = Base64Decoder::with_alphabet(b"-_-_", Alphabet::url_safe());
+        let decoded: Vec<u8> = decoder.collect();
+        assert_eq!(decoded, b"\xfb\xff\xbf");
+    }
+
+    #[test]
+    fn test_alphabet_rejects_wrong_characters() {
+        let mut decoder = Base64Decoder::with_alphabet(b"-_-_", Alphabet::standard());
+        while let Some(_) = decoder.next() { }
+        assert!(decoder.status().is_err());
+
+        let mut decoder = Base64Decoder::with_alphabet(b"Zm9v", Alphabet::bcrypt());
+        while let Some(_) = decoder.next() { }
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_constant_time_round_trip() {
+        let test_wrapper = |s: &str| -> String {
+            let encoder = Base64Encoder::new_constant_time(s.as_bytes());
+            String::from_utf8(encoder.collect()).unwrap()
+        };
+        assert_eq!(test_wrapper("foobar"), "Zm9vYmFy");
+
+        let mut decoder = Base64Decoder::new_constant_time(b"Zm9vYmFy");
+        let decoded: Vec<u8> = decoder.by_ref().collect();
+        assert_eq!(decoded, b"foobar");
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_no_padding_round_trip() {
+        let test_wrapper = |s: &str| -> String {
+            let encoder = Base64Encoder::new(s.as_bytes()).no_padding();
+            String::from_utf8(encoder.collect()).unwrap()
+        };
+        assert_eq!(test_wrapper("f"), "Zg");
+        assert_eq!(test_wrapper("fo"), "Zm8");
+        assert_eq!(test_wrapper("foo"), "Zm9v");
+
+        let decode_wrapper = |s: &str| -> Vec<u8> {
+            let decoder = Base64Decoder::new(s.as_bytes()).no_padding();
+            decoder.collect()
+        };
+        assert_eq!(decode_wrapper("Zg"), b"f");
+        assert_eq!(decode_wrapper("Zm8"), b"fo");
+        assert_eq!(decode_wrapper("Zm9v"), b"foo");
+    }
+
+    #[test]
+    fn test_no_padding_rejects_dangling_character() {
+        let mut decoder = Base64Decoder::new(b"Zm9vYg").no_padding();
+        while let Some(_) = decoder.next() { }
+        assert!(decoder.status().is_ok());
+
+        let mut decoder = Base64Decoder::new(b"Z").no_padding();
+        while let Some(_) = decoder.next() { }
+        assert!(decoder.status().is_err());
+    }
+
+    #[test]
+    fn test_no_padding_composes_with_alphabet() {
+        let encoder = Base64Encoder::with_alphabet(b"\xfb\xff\xbf", Alphabet::url_safe()).no_padding();
+        let encoded: Vec<u8> = encoder.collect();
+        assert_eq!(encoded, b"-_-_");
+
+        let decoder = Base64Decoder::with_alphabet(b"-_-_", Alphabet::url_safe()).no_padding();
+        let decoded: Vec<u8> = decoder.collect();
+        assert_eq!(decoded, b"\xfb\xff\xbf");
+    }
+
+    #[test]
+    fn test_line_wrapping_round_trip() {
+        let data = [0u8; 12]; // encodes to 16 standard base64 characters
+        let encoder = Base64Encoder::new(&data);
+        let wrapped: Vec<u8> = Base64Lines::new(encoder, 4, LineEnding::LF).collect();
+        assert_eq!(wrapped, b"AAAA\nAAAA\nAAAA\nAAAA");
+
+        let mut decoder = Base64Decoder::new(&wrapped).from_lines();
+        let decoded: Vec<u8> = decoder.by_ref().collect();
+        assert_eq!(decoded, &data[..]);
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_line_wrapping_composes_with_alphabet() {
+        let encoder = Base64Encoder::with_alphabet(b"\xfb\xff\xbf", Alphabet::bcrypt());
+        let wrapped: Vec<u8> = Base64Lines::new(encoder, 2, LineEnding::LF).collect();
+
+        let mut decoder = Base64Decoder::with_alphabet(&wrapped, Alphabet::bcrypt()).from_lines();
+        let decoded: Vec<u8> = decoder.by_ref().collect();
+        assert_eq!(decoded, b"\xfb\xff\xbf");
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_line_wrapping_crlf() {
+        let data = b"foobar";
+        let encoder = Base64Encoder::new(&data[..]);
+        let wrapped: Vec<u8> = Base64Lines::new(encoder, 4, LineEnding::CRLF).collect();
+        assert_eq!(wrapped, b"Zm9v\r\nYmFy");
+    }
+
+    #[test]
+    fn test_stream_decoder_arbitrary_fragments() {
+        let mut decoder = Base64StreamDecoder::new();
+        let mut result: Vec<u8> = Vec::new();
+        result.extend(decoder.feed(b"Z"));
+        result.extend(decoder.feed(b"m9vYm"));
+        result.extend(decoder.feed(b"Fy"));
+        result.extend(decoder.finish());
+        assert_eq!(result, b"foobar");
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_stream_decoder_invalid() {
+        let mut decoder = Base64StreamDecoder::new();
+        let mut result: Vec<u8> = Vec::new();
+        result.extend(decoder.feed(b"Zm$v"));
+        result.extend(decoder.finish());
+        assert!(decoder.status().is_err());
+    }
+
+    #[test]
+    fn test_stream_decoder_no_padding_trailing_group() {
+        let mut decoder = Base64StreamDecoder::new().no_padding();
+        let mut result: Vec<u8> = Vec::new();
+        result.extend(decoder.feed(b"Zm9v"));
+        result.extend(decoder.feed(b"Yg"));
+        result.extend(decoder.finish());
+        assert_eq!(result, b"foob");
+        assert!(decoder.status().is_ok());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(format!("{}", Base64Display::new(b"")), "");
+        assert_eq!(format!("{}", Base64Display::new(b"f")), "Zg==");
+        assert_eq!(format!("{}", Base64Display::new(b"foobar")), "Zm9vYmFy");
+    }
+
     #[test]
     fn test_base64_random() {
         use rand::{thread_rng, Rng};